@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+// Bounded chunk list the decoder thread fills and the cpal callback drains.
+// At most `max_chunks` blocks sit buffered ahead.
+pub struct ChunkBuffer {
+    inner: Mutex<Inner>,
+    space: Condvar, // signalled when a chunk is dropped (room to produce)
+    max_chunks: usize,
+}
+
+struct Inner {
+    chunks: VecDeque<Vec<f32>>,
+    cursor: usize, // consumer offset into the front chunk
+}
+
+impl ChunkBuffer {
+    pub fn new(max_chunks: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                chunks: VecDeque::new(),
+                cursor: 0,
+            }),
+            space: Condvar::new(),
+            max_chunks,
+        }
+    }
+
+    // Append a block, blocking while full. Returns false if `stop` is raised.
+    pub fn produce(&self, chunk: Vec<f32>, stop: &AtomicBool) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        while inner.chunks.len() >= self.max_chunks {
+            if stop.load(Ordering::SeqCst) {
+                return false;
+            }
+            let (guard, _) = self
+                .space
+                .wait_timeout(inner, Duration::from_millis(100))
+                .unwrap();
+            inner = guard;
+        }
+        inner.chunks.push_back(chunk);
+        true
+    }
+
+    // Copy up to out.len() samples, padding with silence on underrun.
+    // Returns the number of real (non-silence) samples written.
+    pub fn consume_exact(&self, out: &mut [f32]) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        let mut written = 0;
+        let mut freed = false;
+
+        while written < out.len() {
+            let Some(front) = inner.chunks.front() else {
+                break;
+            };
+            let front_len = front.len();
+            let available = front_len - inner.cursor;
+            let want = (out.len() - written).min(available);
+            let start = inner.cursor;
+            out[written..written + want].copy_from_slice(&front[start..start + want]);
+            written += want;
+            inner.cursor += want;
+
+            if inner.cursor >= front_len {
+                inner.chunks.pop_front();
+                inner.cursor = 0;
+                freed = true;
+            }
+        }
+
+        for sample in &mut out[written..] {
+            *sample = 0.0;
+        }
+
+        if freed {
+            self.space.notify_one();
+        }
+
+        written
+    }
+
+    // Samples still buffered across all chunks.
+    pub fn samples_available(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        let front = inner
+            .chunks
+            .front()
+            .map_or(0, |c| c.len() - inner.cursor);
+        front + inner.chunks.iter().skip(1).map(Vec::len).sum::<usize>()
+    }
+
+    // Drop all buffered chunks and reset the cursor.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.chunks.clear();
+        inner.cursor = 0;
+        self.space.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consume_spans_multiple_chunks() {
+        let buffer = ChunkBuffer::new(4);
+        let stop = AtomicBool::new(false);
+        buffer.produce(vec![1.0, 2.0], &stop);
+        buffer.produce(vec![3.0, 4.0], &stop);
+
+        let mut out = [0.0; 3];
+        buffer.consume_exact(&mut out);
+        assert_eq!(out, [1.0, 2.0, 3.0]);
+        assert_eq!(buffer.samples_available(), 1);
+    }
+
+    #[test]
+    fn underrun_pads_with_silence() {
+        let buffer = ChunkBuffer::new(4);
+        let stop = AtomicBool::new(false);
+        buffer.produce(vec![1.0], &stop);
+
+        let mut out = [9.0; 3];
+        buffer.consume_exact(&mut out);
+        assert_eq!(out, [1.0, 0.0, 0.0]);
+        assert_eq!(buffer.samples_available(), 0);
+    }
+
+    #[test]
+    fn produce_fails_fast_when_stopped() {
+        let buffer = ChunkBuffer::new(1);
+        let stop = AtomicBool::new(false);
+        assert!(buffer.produce(vec![1.0], &stop));
+        // Buffer is now full; a stopped producer must not block forever.
+        stop.store(true, Ordering::SeqCst);
+        assert!(!buffer.produce(vec![2.0], &stop));
+    }
+
+    #[test]
+    fn clear_drops_everything() {
+        let buffer = ChunkBuffer::new(4);
+        let stop = AtomicBool::new(false);
+        buffer.produce(vec![1.0, 2.0], &stop);
+        buffer.clear();
+        assert_eq!(buffer.samples_available(), 0);
+    }
+}
@@ -0,0 +1,169 @@
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder as SymphoniaDecoder, DecoderOptions};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
+
+// Pull-style wrapper over Symphonia: open a source, then ask for the next block
+// of interleaved f32 frames until it runs dry.
+pub struct Decoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn SymphoniaDecoder>,
+    track_id: u32,
+    sample_rate: u32,
+    channels: usize,
+    replay_gain: Option<f32>,
+    buf: Option<SampleBuffer<f32>>,
+}
+
+impl Decoder {
+    // Open a local file, hinting the container from its extension.
+    pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let ext = Path::new(path).extension().and_then(|e| e.to_str());
+        Self::from_source(mss, ext)
+    }
+
+    // Open a streaming transport, hinting the container from `ext` when known.
+    pub fn open_stream(
+        reader: crate::net::Reader,
+        ext: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mss = MediaSourceStream::new(Box::new(reader), Default::default());
+        Self::from_source(mss, ext)
+    }
+
+    fn from_source(
+        mss: MediaSourceStream,
+        ext: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut hint = Hint::new();
+        if let Some(ext) = ext {
+            hint.with_extension(ext);
+        }
+
+        let probed = symphonia::default::get_probe().format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?;
+        let mut format = probed.format;
+
+        let replay_gain = parse_track_gain(format.metadata().current());
+
+        let track = format
+            .default_track()
+            .ok_or("No default track in media source")?;
+        let track_id = track.id;
+
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or("Unknown sample rate")?;
+        let channels = track
+            .codec_params
+            .channels
+            .map(|c| c.count())
+            .ok_or("Unknown channel layout")?;
+
+        Ok(Self {
+            format,
+            decoder,
+            track_id,
+            sample_rate,
+            channels,
+            replay_gain,
+            buf: None,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    // ReplayGain track gain as a linear multiplier, if the file carried the tag.
+    pub fn replay_gain(&self) -> Option<f32> {
+        self.replay_gain
+    }
+
+    // Seek to `target` (via the format's TimeBase) and reset the decoder. WAV
+    // goes through the same path as every other container.
+    pub fn seek(&mut self, target: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        self.format.seek(
+            SeekMode::Accurate,
+            SeekTo::Time {
+                time: Time::from(target.as_secs_f64()),
+                track_id: Some(self.track_id),
+            },
+        )?;
+        self.decoder.reset();
+        Ok(())
+    }
+
+    // Decode the next packet into interleaved f32 frames; `Ok(None)` at EOF.
+    // Foreign-track packets and recoverable decode errors are skipped.
+    pub fn next_frames(&mut self) -> Result<Option<Vec<f32>>, Box<dyn std::error::Error>> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(err))
+                    if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(None);
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            match self.decoder.decode(&packet) {
+                Ok(decoded) => {
+                    if self.buf.is_none() {
+                        let spec = *decoded.spec();
+                        let duration = decoded.capacity() as u64;
+                        self.buf = Some(SampleBuffer::<f32>::new(duration, spec));
+                    }
+
+                    let buf = self.buf.as_mut().unwrap();
+                    buf.copy_interleaved_ref(decoded);
+                    return Ok(Some(buf.samples().to_vec()));
+                }
+                // A malformed packet is not fatal: drop it and keep decoding.
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+// Parse a track-gain tag (e.g. "-6.48 dB") into a linear multiplier.
+fn parse_track_gain(meta: Option<&MetadataRevision>) -> Option<f32> {
+    let meta = meta?;
+    for tag in meta.tags() {
+        if tag.std_key == Some(StandardTagKey::ReplayGainTrackGain) {
+            let value = tag.value.to_string();
+            let db: f32 = value.split_whitespace().next()?.parse().ok()?;
+            return Some(10f32.powf(db / 20.0));
+        }
+    }
+    None
+}
+
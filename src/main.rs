@@ -1,14 +1,62 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 use std::time::Duration;
-use std::collections::VecDeque;
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::Stream;
 
 use crossterm::event::{self, Event, KeyCode};
-use hound::WavReader;
 use walkdir::WalkDir;
 
+mod buffer;
+mod decoder;
+mod net;
+mod resampler;
+use buffer::ChunkBuffer;
+use decoder::Decoder;
+use net::Reader;
+use resampler::Resampler;
+
+// Volume units moved per key press.
+const VOLUME_STEP: u8 = 5;
+// Divisor mapping the integer level onto a 0.0–1.0 gain.
+const VOLUME_REDUCTION: f32 = 75.0;
+
+// Samples per produced block.
+const CHUNK_SIZE: usize = 8192;
+// Chunks buffered ahead before the decoder back-pressures.
+const MAX_CHUNKS: usize = 8;
+// Where the playback position is saved across restarts.
+const STATE_FILE: &str = ".audioplayer_state";
+
+// An optional one-shot intro followed by a body looped on source-frame offsets
+// (`loop_end == None` wraps at end of file).
+#[derive(Clone)]
+struct LoopTrack {
+    intro: Option<String>,
+    loop_start: u64,
+    loop_end: Option<u64>,
+}
+
+// Intro/loop state handed to the decoder thread.
+#[derive(Clone)]
+struct LoopSpec {
+    intro: Option<String>,
+    play_intro: bool,
+    loop_start: u64,
+    loop_end: Option<u64>,
+}
+
+// A persistable snapshot of playback position.
+#[derive(Clone, Copy)]
+struct PlaybackState {
+    track_index: usize,
+    sample_index: usize,
+    playing_intro: bool,
+}
+
 struct AudioPlayer {
     // Player settings:
     playlist: Vec<String>,
@@ -23,8 +71,21 @@ struct AudioPlayer {
     stream: Option<Stream>,
 
     // Samples:
-    samples: Arc<Mutex<VecDeque<f32>>>, // `VecDeque` allows for fast and constant-time appending at the back and removing from the front
+    buffer: Arc<ChunkBuffer>, // Bounded producer/consumer buffer fed by the decoder thread
     sample_index: Arc<Mutex<usize>>, // Track the current sample index for pause/resume
+
+    // Background decoder:
+    decoder_handle: Option<JoinHandle<()>>, // Decoder thread for the current track
+    stop: Arc<AtomicBool>, // Signals the decoder thread to exit
+
+    // Looping:
+    loops: HashMap<usize, LoopTrack>, // Per-track intro/loop metadata, keyed by track index
+    playing_intro: bool, // Whether the current track started from its one-shot intro
+
+    // Loudness:
+    volume_level: u8, // User volume as an integer level, stepped by the keybindings
+    volume: Arc<Mutex<f32>>, // Perceptual gain (0.0–1.0) the callback multiplies every sample by
+    gain: Arc<Mutex<f32>>, // Per-track ReplayGain multiplier applied before the user volume
 }
 
 impl AudioPlayer {
@@ -40,64 +101,95 @@ impl AudioPlayer {
             config: config.clone(),
             stream: None,
 
-            samples: Arc::new(Mutex::new(VecDeque::new())),
+            buffer: Arc::new(ChunkBuffer::new(MAX_CHUNKS)),
             sample_index: Arc::new(Mutex::new(0)), // Start at the beginning
+
+            decoder_handle: None,
+            stop: Arc::new(AtomicBool::new(false)),
+
+            loops: HashMap::new(),
+            playing_intro: false,
+
+            volume_level: (VOLUME_REDUCTION / 2.0) as u8, // Roughly half volume to start
+            volume: Arc::new(Mutex::new((VOLUME_REDUCTION / 2.0) as u8 as f32 / VOLUME_REDUCTION)),
+            gain: Arc::new(Mutex::new(1.0)),
         }
     }
 
-    fn process_samples(&mut self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Spawn the decoder thread for the current track.
+    fn start_decoder(&mut self, file_path: String, start: Duration) {
         println!("Loading file: {}", file_path);
 
-        let mut reader = WavReader::open(file_path)?;
-        let spec = reader.spec();
-
-        if spec.sample_rate != self.sample_rate {
-            eprintln!(
-                "Sample rate mismatch. File has {}, expected {}.",
-                spec.sample_rate, self.sample_rate
+        // Fresh stop flag for the new thread; the old one may still be `true`.
+        self.stop = Arc::new(AtomicBool::new(false));
+
+        let buffer = Arc::clone(&self.buffer);
+        let stop = Arc::clone(&self.stop);
+        let gain = Arc::clone(&self.gain);
+        let target_rate = self.sample_rate;
+        let target_channels = self.channels;
+        let loop_spec = self.loops.get(&self.track_index).map(|lt| LoopSpec {
+            intro: lt.intro.clone(),
+            play_intro: self.playing_intro,
+            loop_start: lt.loop_start,
+            loop_end: lt.loop_end,
+        });
+
+        let handle = std::thread::spawn(move || {
+            decode_loop(
+                &file_path,
+                &buffer,
+                &stop,
+                &gain,
+                target_rate,
+                target_channels,
+                start,
+                loop_spec,
             );
-            return Err("Sample rate mismatch".into());
-        }
+        });
+        self.decoder_handle = Some(handle);
+    }
 
-        let mut samples = self.samples.lock().unwrap();
-        samples.clear(); 
+    // Attach intro/loop metadata to a playlist entry.
+    fn register_loop(&mut self, track_index: usize, loop_track: LoopTrack) {
+        self.loops.insert(track_index, loop_track);
+    }
 
-        for sample in reader.samples::<i16>() {
-            let normalized_sample = sample.unwrap() as f32 / i16::MAX as f32;
-            samples.push_back(normalized_sample);
+    // Stop the decoder thread and wait for it to exit.
+    fn stop_decoder(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.buffer.clear(); // Wake any `produce` blocked on backpressure
+        if let Some(handle) = self.decoder_handle.take() {
+            let _ = handle.join();
         }
-
-        println!("Loaded {} samples.", samples.len());
-
-        Ok(())
     }
 
     fn play(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let file_path = self.playlist[self.track_index].clone();
 
-        if self.samples.lock().unwrap().is_empty() {
-            self.process_samples(&file_path)?;
+        // Only spawn the decoder for a fresh track; resuming reuses the buffer
+        // the existing thread is already filling.
+        if self.decoder_handle.is_none() {
+            self.start_decoder(file_path.clone(), Duration::ZERO);
         }
 
-        let samples = Arc::clone(&self.samples);
+        let buffer = Arc::clone(&self.buffer);
         let config = self.config.clone();
-        let channels = self.channels;
         let sample_index = Arc::clone(&self.sample_index);
+        let volume = Arc::clone(&self.volume);
+        let gain = Arc::clone(&self.gain);
 
         let stream = self.device.build_output_stream(
             &config,
             move |output: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                let mut samples = samples.lock().unwrap();
-                let mut sample_index = sample_index.lock().unwrap();
+                let written = buffer.consume_exact(output);
 
-                for frame in output.chunks_mut(channels) {
-                    for sample in frame {
-                        *sample = samples.pop_front().unwrap_or(0.0);
-                        *sample_index += 1;
-                    }
+                let level = *volume.lock().unwrap() * *gain.lock().unwrap();
+                let mut sample_index = sample_index.lock().unwrap();
+                for sample in output.iter_mut() {
+                    *sample *= level;
                 }
-
-                *sample_index = *sample_index;
+                *sample_index += written;
             },
             |err| eprintln!("Error occurred on stream: {}", err),
             None,
@@ -118,7 +210,7 @@ impl AudioPlayer {
             self.stream = None;
             self.is_playing = false;
         } else {
-            
+
             println!("Resumed from sample index: {}", self.sample_index.lock().unwrap());
             if let Err(err) = self.play() {
                 eprintln!("Failed to resume playback: {}", err);
@@ -146,38 +238,429 @@ impl AudioPlayer {
         } else {
             self.track_index -= 1;
         }
-        
+
         self.force_play();
     }
 
     fn force_play(&mut self) {
+        self.stop_decoder();
         self.reset_samples();
 
+        // A fresh loop track starts from its intro (if it has one).
+        self.playing_intro = self
+            .loops
+            .get(&self.track_index)
+            .is_some_and(|lt| lt.intro.is_some());
+
         if let Err(err) = self.play() {
             eprintln!("Failed to play file: {}", err);
         }
     }
 
+    // Capture the current position for `set_state`/`save_state`.
+    fn get_state(&self) -> PlaybackState {
+        PlaybackState {
+            track_index: self.track_index,
+            sample_index: *self.sample_index.lock().unwrap(),
+            playing_intro: self.playing_intro,
+        }
+    }
+
+    // Restore a captured position and resume from it.
+    fn set_state(&mut self, state: PlaybackState) {
+        self.stop_decoder();
+        self.buffer.clear();
+
+        self.track_index = state.track_index.min(self.playlist.len() - 1);
+        self.playing_intro = state.playing_intro;
+
+        let frames = state.sample_index / self.channels.max(1);
+        let target = Duration::from_secs_f64(frames as f64 / self.sample_rate as f64);
+
+        let file_path = self.playlist[self.track_index].clone();
+        self.start_decoder(file_path, target);
+        *self.sample_index.lock().unwrap() = state.sample_index;
+
+        if let Err(err) = self.play() {
+            eprintln!("Failed to restore playback: {}", err);
+        }
+    }
+
+    // Playback position, from the samples the callback has consumed.
+    fn elapsed(&self) -> Duration {
+        let index = *self.sample_index.lock().unwrap();
+        let frames = index / self.channels.max(1);
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
+    // Flush the buffer and restart decoding at `target`.
+    fn seek(&mut self, target: Duration) {
+        let file_path = self.playlist[self.track_index].clone();
+
+        self.stop_decoder();
+        self.buffer.clear();
+        self.start_decoder(file_path, target);
+
+        // Seed the position readout so `elapsed` reflects where we jumped to.
+        let offset =
+            (target.as_secs_f64() * self.sample_rate as f64 * self.channels as f64) as usize;
+        *self.sample_index.lock().unwrap() = offset;
+
+        if let Err(err) = self.play() {
+            eprintln!("Failed to seek: {}", err);
+            return;
+        }
+
+        println!("Position: {}", format_timestamp(target));
+    }
+
+    fn volume_up(&mut self) {
+        self.set_volume_level(self.volume_level.saturating_add(VOLUME_STEP));
+    }
+
+    fn volume_down(&mut self) {
+        self.set_volume_level(self.volume_level.saturating_sub(VOLUME_STEP));
+    }
+
+    fn set_volume_level(&mut self, level: u8) {
+        // Clamp so the perceptual gain stays within 0.0–1.0.
+        self.volume_level = level.min(VOLUME_REDUCTION as u8);
+        let volume = self.volume_level as f32 / VOLUME_REDUCTION;
+        *self.volume.lock().unwrap() = volume;
+        println!("Volume: {}%", (volume * 100.0).round() as u32);
+    }
+
     fn reset_samples(&mut self) {
         *self.sample_index.lock().unwrap() = 0;
-        self.samples.lock().unwrap().clear();
+        self.buffer.clear();
+    }
+}
+
+// Decoder-thread body: play the optional intro, then the looping/plain body.
+fn decode_loop(
+    path: &str,
+    buffer: &ChunkBuffer,
+    stop: &AtomicBool,
+    gain: &Mutex<f32>,
+    target_rate: u32,
+    target_channels: usize,
+    start: Duration,
+    loop_spec: Option<LoopSpec>,
+) {
+    // A one-shot intro plays through once before the body begins.
+    if let Some(spec) = &loop_spec {
+        if spec.play_intro {
+            if let Some(intro) = &spec.intro {
+                if !decode_segment(intro, buffer, stop, gain, target_rate, target_channels, 0, None)
+                {
+                    return;
+                }
+            }
+        }
+    }
+
+    let (loop_start, loop_end) = match &loop_spec {
+        Some(spec) => (spec.loop_start, spec.loop_end),
+        None => (0, None),
+    };
+
+    decode_body(
+        path,
+        buffer,
+        stop,
+        gain,
+        target_rate,
+        target_channels,
+        start,
+        loop_start,
+        loop_end,
+        loop_spec.is_some(),
+    );
+}
+
+// Open a decoder, routing stream URLs through the network transport.
+fn open_decoder(path: &str) -> Result<Decoder, Box<dyn std::error::Error>> {
+    if Reader::is_url(path) {
+        let reader = Reader::connect(path)?;
+        Decoder::open_stream(reader, Reader::hint_extension(path))
+    } else {
+        Decoder::open(path)
+    }
+}
+
+// Decode `path` once into the buffer. Returns false if `stop` was raised.
+fn decode_segment(
+    path: &str,
+    buffer: &ChunkBuffer,
+    stop: &AtomicBool,
+    gain: &Mutex<f32>,
+    target_rate: u32,
+    target_channels: usize,
+    start_frame: u64,
+    end_frame: Option<u64>,
+) -> bool {
+    decode_body(
+        path,
+        buffer,
+        stop,
+        gain,
+        target_rate,
+        target_channels,
+        Duration::ZERO,
+        start_frame,
+        end_frame,
+        false,
+    )
+}
+
+// Decode a track body, wrapping to `loop_start` at `loop_end` (or EOF) when
+// `looping`. The resampler is reset on the wrap so it doesn't bridge the seam.
+#[allow(clippy::too_many_arguments)]
+fn decode_body(
+    path: &str,
+    buffer: &ChunkBuffer,
+    stop: &AtomicBool,
+    gain: &Mutex<f32>,
+    target_rate: u32,
+    target_channels: usize,
+    start: Duration,
+    loop_start: u64,
+    loop_end: Option<u64>,
+    looping: bool,
+) -> bool {
+    let mut decoder = match open_decoder(path) {
+        Ok(decoder) => decoder,
+        Err(err) => {
+            eprintln!("Failed to open {}: {}", path, err);
+            return true;
+        }
+    };
+
+    // Fold the track's ReplayGain (if any) into the per-track gain so
+    // consecutive tracks play back loudness-matched.
+    *gain.lock().unwrap() = decoder.replay_gain().unwrap_or(1.0);
+
+    let src_rate = decoder.sample_rate();
+    let src_channels = decoder.channels();
+
+    // Resample on the fly when the source rate differs from the device rate,
+    // so the output stream can stay fixed regardless of what we open.
+    let mut resampler = if src_rate != target_rate {
+        Some(Resampler::new(src_rate, target_rate, src_channels))
+    } else {
+        None
+    };
+
+    let mut pos_frame = if !start.is_zero() {
+        if let Err(err) = decoder.seek(start) {
+            eprintln!("Seek failed: {}", err);
+        }
+        (start.as_secs_f64() * src_rate as f64) as u64
+    } else {
+        0
+    };
+
+    let wrap = |decoder: &mut Decoder| -> bool {
+        let target = Duration::from_secs_f64(loop_start as f64 / src_rate as f64);
+        decoder.seek(target).is_ok()
+    };
+
+    let mut pending: Vec<f32> = Vec::new();
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        match decoder.next_frames() {
+            Ok(Some(mut frames)) => {
+                // Truncate the block at the loop end so we wrap on a frame boundary.
+                if let Some(end) = loop_end {
+                    let frame_count = (frames.len() / src_channels) as u64;
+                    if pos_frame + frame_count > end {
+                        let keep = (end - pos_frame) as usize * src_channels;
+                        frames.truncate(keep);
+                    }
+                }
+                pos_frame += (frames.len() / src_channels) as u64;
+
+                let frames = match resampler {
+                    Some(ref mut resampler) => resampler.process(&frames),
+                    None => frames,
+                };
+                // Map the source channel layout onto the device's before buffering.
+                let frames = remix(frames, src_channels, target_channels);
+                if !push_samples(&mut pending, frames, buffer, stop) {
+                    return false;
+                }
+
+                if looping && loop_end == Some(pos_frame) {
+                    if !wrap(&mut decoder) {
+                        return true;
+                    }
+                    // Drop the resampler's carried frame so it doesn't interpolate
+                    // across the loop seam.
+                    if let Some(resampler) = resampler.as_mut() {
+                        resampler.reset();
+                    }
+                    pos_frame = loop_start;
+                }
+            }
+            Ok(None) => {
+                if looping && loop_end.is_none() {
+                    if !wrap(&mut decoder) {
+                        return true;
+                    }
+                    if let Some(resampler) = resampler.as_mut() {
+                        resampler.reset();
+                    }
+                    pos_frame = loop_start;
+                    continue;
+                }
+                // Flush the trailing partial chunk at end of stream.
+                if !pending.is_empty() {
+                    let _ = buffer.produce(std::mem::take(&mut pending), stop);
+                }
+                return true;
+            }
+            Err(err) => {
+                eprintln!("Decode error: {}", err);
+                return true;
+            }
+        }
+    }
+}
+
+// Map `from` source channels onto `to` device channels.
+fn remix(input: Vec<f32>, from: usize, to: usize) -> Vec<f32> {
+    if from == to || from == 0 {
+        return input;
+    }
+
+    let frames = input.len() / from;
+    let mut out = Vec::with_capacity(frames * to);
+    for frame in input.chunks_exact(from) {
+        if to == 1 {
+            out.push(frame.iter().sum::<f32>() / from as f32);
+        } else if from == 1 {
+            out.extend(std::iter::repeat(frame[0]).take(to));
+        } else {
+            for channel in 0..to {
+                out.push(frame.get(channel).copied().unwrap_or(0.0));
+            }
+        }
+    }
+    out
+}
+
+// Batch samples into `CHUNK_SIZE` blocks. Returns false if `stop` was raised.
+fn push_samples(
+    pending: &mut Vec<f32>,
+    samples: Vec<f32>,
+    buffer: &ChunkBuffer,
+    stop: &AtomicBool,
+) -> bool {
+    pending.extend(samples);
+    while pending.len() >= CHUNK_SIZE {
+        let chunk: Vec<f32> = pending.drain(..CHUNK_SIZE).collect();
+        if !buffer.produce(chunk, stop) {
+            return false;
+        }
+    }
+    true
+}
+
+// Format a duration as `mm:ss`.
+fn format_timestamp(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+// Persist a snapshot as one whitespace-separated line.
+fn save_state(state: &PlaybackState) {
+    let line = format!(
+        "{} {} {}",
+        state.track_index, state.sample_index, state.playing_intro
+    );
+    if let Err(err) = std::fs::write(STATE_FILE, line) {
+        eprintln!("Failed to save state: {}", err);
+    } else {
+        println!("Saved playback position.");
+    }
+}
+
+// Read a saved snapshot, if one exists and parses.
+fn load_state() -> Option<PlaybackState> {
+    let contents = std::fs::read_to_string(STATE_FILE).ok()?;
+    let mut fields = contents.split_whitespace();
+    Some(PlaybackState {
+        track_index: fields.next()?.parse().ok()?,
+        sample_index: fields.next()?.parse().ok()?,
+        playing_intro: fields.next()?.parse().ok()?,
+    })
+}
+
+// Register every `*_loop.*` entry as a looping track; a matching `*_intro.*`
+// sibling, when present, becomes its one-shot intro.
+fn register_intro_loops(player: &mut AudioPlayer) {
+    // The intro sibling of a `_loop` entry only plays as part of its loop track,
+    // so drop it from the visible playlist first to keep it off the regular
+    // next/previous rotation (otherwise it would play once standalone and again
+    // as the loop's intro).
+    let mut intros: Vec<String> = Vec::new();
+    for path in &player.playlist {
+        if let Some(pos) = path.rfind("_loop.") {
+            let ext = &path[pos + "_loop.".len()..];
+            let intro = format!("{}_intro.{}", &path[..pos], ext);
+            if player.playlist.contains(&intro) || std::path::Path::new(&intro).exists() {
+                intros.push(intro);
+            }
+        }
+    }
+    player.playlist.retain(|path| !intros.contains(path));
+
+    let playlist = player.playlist.clone();
+    for (index, path) in playlist.iter().enumerate() {
+        let Some(pos) = path.rfind("_loop.") else {
+            continue;
+        };
+        let ext = &path[pos + "_loop.".len()..];
+        let intro = format!("{}_intro.{}", &path[..pos], ext);
+        let intro = if std::path::Path::new(&intro).exists() || intros.contains(&intro) {
+            Some(intro)
+        } else {
+            None
+        };
+        player.register_loop(
+            index,
+            LoopTrack {
+                intro,
+                loop_start: 0,
+                loop_end: None,
+            },
+        );
     }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = std::env::args().collect();
     if args.len() != 2 {
-        eprintln!("Usage: {} <folder_path>", args[0]);
+        eprintln!("Usage: {} <folder_path | stream_url>", args[0]);
         return Ok(());
     }
 
-    let folder_path = &args[1];
+    let source = &args[1];
     let mut playlist: Vec<String> = Vec::new();
 
-    for entry in WalkDir::new(folder_path).into_iter().filter_map(Result::ok) {
-        if let Some(ext) = entry.path().extension() {
-            if ext == "wav" {
-                playlist.push(entry.path().to_string_lossy().to_string());
+    // A `tcp://`/`tcpx://`/`http://` argument is a single live stream; anything
+    // else is a folder to scan for local audio files.
+    if Reader::is_url(source) {
+        playlist.push(source.clone());
+    } else {
+        for entry in WalkDir::new(source).into_iter().filter_map(Result::ok) {
+            if let Some(ext) = entry.path().extension().and_then(|e| e.to_str()) {
+                if matches!(ext, "wav" | "mp3" | "flac" | "ogg" | "m4a") {
+                    playlist.push(entry.path().to_string_lossy().to_string());
+                }
             }
         }
     }
@@ -197,7 +680,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut player = AudioPlayer::new(playlist, device, config);
 
-    player.play()?;
+    // A `*_loop` file with a sibling `*_intro` is treated as an intro-then-loop
+    // track: the intro plays once, then the body repeats forever.
+    register_intro_loops(&mut player);
+
+    // Resume where we left off last time, if a saved position is present.
+    match load_state() {
+        Some(state) => player.set_state(state),
+        None => player.play()?,
+    }
 
     loop {
         if event::poll(Duration::from_millis(250))? {
@@ -206,10 +697,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     KeyCode::Char('p') => player.toggle(),
                     KeyCode::Char('j') => player.previous(),
                     KeyCode::Char('k') => player.next(),
+                    KeyCode::Char('+') | KeyCode::Char('=') => player.volume_up(),
+                    KeyCode::Char('-') => player.volume_down(),
+                    KeyCode::Right => {
+                        let target = player.elapsed() + Duration::from_secs(5);
+                        player.seek(target);
+                    }
+                    KeyCode::Left => {
+                        let target = player.elapsed().saturating_sub(Duration::from_secs(5));
+                        player.seek(target);
+                    }
+                    KeyCode::Char('s') => save_state(&player.get_state()),
                     _ => {}
                 }
             }
         }
     }
 
-}
\ No newline at end of file
+}
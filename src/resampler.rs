@@ -0,0 +1,131 @@
+// Rational resampler: interpolates between input frames to hit the output rate.
+pub struct Resampler {
+    channels: usize,
+    step: f64, // read-position advance per output frame (down/up)
+    pos: f64,  // fractional read position, relative to the carried history frame
+    last: Vec<f32>, // previous call's final frame, for interpolation across buffers
+    have_last: bool,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32, channels: usize) -> Self {
+        let g = gcd(input_rate, output_rate);
+        let up = (output_rate / g) as f64;
+        let down = (input_rate / g) as f64;
+
+        Self {
+            channels,
+            step: down / up,
+            pos: 0.0,
+            last: vec![0.0; channels],
+            have_last: false,
+        }
+    }
+
+    // Forget carried state so the next `process` starts clean (after seek/wrap).
+    pub fn reset(&mut self) {
+        self.pos = 0.0;
+        self.have_last = false;
+        for sample in &mut self.last {
+            *sample = 0.0;
+        }
+    }
+
+    // Resample a block of interleaved f32 frames to the output rate.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let ch = self.channels;
+        let frames_in = input.len() / ch;
+        if frames_in == 0 {
+            return Vec::new();
+        }
+
+        // Work over `[history_frame?, input...]` so the position carried from the
+        // previous buffer interpolates smoothly into this one.
+        let offset = if self.have_last { 1 } else { 0 };
+        let total = frames_in + offset;
+        let hist = self.last.clone();
+
+        let frame = |f: usize, c: usize| -> f32 {
+            if offset == 1 && f == 0 {
+                hist[c]
+            } else {
+                input[(f - offset) * ch + c]
+            }
+        };
+
+        let mut out = Vec::with_capacity(((total as f64 / self.step) as usize + 1) * ch);
+        while self.pos + 1.0 < total as f64 {
+            let idx = self.pos.floor() as usize;
+            let t = (self.pos - idx as f64) as f32;
+            for c in 0..ch {
+                out.push(lerp(frame(idx, c), frame(idx + 1, c), t));
+            }
+            self.pos += self.step;
+        }
+
+        // Stash the final input frame as next call's history and rebase the
+        // position so that frame sits at index 0.
+        self.last.copy_from_slice(&input[(frames_in - 1) * ch..frames_in * ch]);
+        self.have_last = true;
+        self.pos -= (total - 1) as f64;
+        if self.pos < 0.0 {
+            self.pos = 0.0;
+        }
+
+        out
+    }
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        let t = b;
+        b = a % b;
+        a = t;
+    }
+    a
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_rates_pass_through() {
+        let mut r = Resampler::new(48_000, 48_000, 1);
+        let out = r.process(&[0.0, 0.25, 0.5, 1.0]);
+        // The final frame is held back as history and comes out on the next call.
+        assert_eq!(out, vec![0.0, 0.25, 0.5]);
+    }
+
+    #[test]
+    fn upsampling_doubles_frame_count() {
+        // 2x up: expect roughly twice as many output frames over a long input.
+        let mut r = Resampler::new(24_000, 48_000, 1);
+        let input: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let out = r.process(&input);
+        assert!((out.len() as i32 - 200).abs() <= 2, "got {}", out.len());
+    }
+
+    #[test]
+    fn halfway_point_interpolates() {
+        // 2x up over a ramp: the frame between 0.0 and 1.0 should be ~0.5.
+        let mut r = Resampler::new(24_000, 48_000, 1);
+        let out = r.process(&[0.0, 1.0, 2.0]);
+        assert!((out[1] - 0.5).abs() < 1e-4, "got {}", out[1]);
+    }
+
+    #[test]
+    fn channels_stay_independent() {
+        let mut r = Resampler::new(24_000, 48_000, 2);
+        // Interleaved stereo: left ramps, right is constant.
+        let out = r.process(&[0.0, 9.0, 2.0, 9.0, 4.0, 9.0]);
+        // Every right-channel sample must remain its constant value.
+        for frame in out.chunks_exact(2) {
+            assert!((frame[1] - 9.0).abs() < 1e-4, "got {}", frame[1]);
+        }
+    }
+}